@@ -2,24 +2,30 @@ use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criteri
 use sqlite_vector::{Config, Vector, VectorDB};
 use tempfile::NamedTempFile;
 
-fn bench_insert(c: &mut Criterion) {
+fn bench_insert_batch(c: &mut Criterion) {
     let temp_file = NamedTempFile::new().unwrap();
     let db = VectorDB::new(temp_file.path(), Config::default()).unwrap();
 
+    // Representative of real ingestion: rows arrive via `insert_batch` and an
+    // explicit `flush`, not one `insert` per row, so the benchmark measures
+    // the batched write path's amortized per-row cost.
+    let batch_size: u64 = 100;
+
     let mut group = c.benchmark_group("insert");
-    group.throughput(Throughput::Elements(1));
+    group.throughput(Throughput::Elements(batch_size));
 
-    group.bench_function("insert_128d", |b| {
-        let mut counter = 0;
+    group.bench_function("insert_batch_128d", |b| {
+        let mut counter = 0usize;
         b.iter(|| {
-            let vector = Vector::from_slice(&vec![0.1f32; 128]);
-            db.insert(
-                &format!("doc{}", counter),
-                black_box(vector),
-                r#"{"test": "data"}"#,
-            )
-            .unwrap();
-            counter += 1;
+            let items: Vec<_> = (0..batch_size)
+                .map(|_| {
+                    let id = format!("doc{}", counter);
+                    counter += 1;
+                    (id, Vector::from_slice(&vec![0.1f32; 128]), r#"{"test": "data"}"#.to_string())
+                })
+                .collect();
+            db.insert_batch(black_box(items)).unwrap();
+            db.flush().unwrap();
         });
     });
 
@@ -65,5 +71,5 @@ fn bench_cosine_similarity(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_insert, bench_search, bench_cosine_similarity);
+criterion_group!(benches, bench_insert_batch, bench_search, bench_cosine_similarity);
 criterion_main!(benches);
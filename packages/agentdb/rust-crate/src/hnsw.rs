@@ -0,0 +1,487 @@
+//! HNSW (Hierarchical Navigable Small World) approximate nearest-neighbor index.
+//!
+//! `VectorDB::search` falls back to a full scan when this feature is disabled or
+//! `Config::hnsw` is `None`, which is correct but collapses past a few thousand
+//! rows. Once an index is configured, `search` answers from the graph alone,
+//! which holds only ids inserted while the index was configured. This module maintains a multi-layer proximity graph alongside the
+//! `vectors` table: each inserted id is assigned a maximum layer from a
+//! geometric distribution, linked to its nearest neighbors at every layer up to
+//! that maximum, and queries descend the graph greedily before expanding a
+//! bounded beam at layer 0. Graph adjacency is persisted in SQLite and cached
+//! in memory, rebuilding lazily the first time an index is opened.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{Vector, VectorDBError};
+
+/// HNSW index configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Maximum number of neighbors linked per node at layers above 0.
+    pub m: usize,
+    /// Maximum number of neighbors linked per node at layer 0 (typically `2 * m`).
+    pub m_max: usize,
+    /// Size of the dynamic candidate list maintained while inserting.
+    pub ef_construction: usize,
+    /// Size of the candidate beam maintained while searching.
+    pub ef_search: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            m_max: 32,
+            ef_construction: 200,
+            ef_search: 64,
+        }
+    }
+}
+
+struct Graph {
+    /// Highest layer assigned to any node, and the id that holds it.
+    entry_point: Option<(String, usize)>,
+    /// `levels[l]` maps a node id to its neighbor ids at layer `l`.
+    levels: Vec<HashMap<String, Vec<String>>>,
+}
+
+impl Graph {
+    fn empty() -> Self {
+        Self {
+            entry_point: None,
+            levels: Vec::new(),
+        }
+    }
+
+    fn ensure_level(&mut self, level: usize) {
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, HashMap::new);
+        }
+    }
+}
+
+/// A persistent, cached HNSW index over the `vectors` table.
+pub struct HnswIndex {
+    conn: Arc<RwLock<Connection>>,
+    config: Config,
+    graph: RwLock<Graph>,
+    /// Decoded-vector cache keyed by id, avoiding a `vectors` round-trip for
+    /// every distance computation during construction and search.
+    vector_cache: RwLock<HashMap<String, Vector>>,
+}
+
+impl HnswIndex {
+    /// Open (creating if necessary) the HNSW index backing `conn`, rebuilding
+    /// the in-memory graph cache from the persisted adjacency table.
+    pub(crate) fn open(conn: Arc<RwLock<Connection>>, config: Config) -> Result<Self, VectorDBError> {
+        {
+            let c = conn.write();
+            c.execute_batch(
+                "CREATE TABLE IF NOT EXISTS hnsw_edges (
+                    node_id TEXT NOT NULL,
+                    level INTEGER NOT NULL,
+                    neighbor_id TEXT NOT NULL,
+                    PRIMARY KEY (node_id, level, neighbor_id)
+                );
+                CREATE TABLE IF NOT EXISTS hnsw_meta (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );",
+            )?;
+        }
+
+        let graph = Self::load_graph(&conn.read())?;
+
+        Ok(Self {
+            conn,
+            config,
+            graph: RwLock::new(graph),
+            vector_cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Discard the in-memory graph and vector cache and rebuild them from the
+    /// persisted `hnsw_edges`/`hnsw_meta` tables. Used after
+    /// [`crate::VectorDB::restore`] overwrites those tables wholesale out from
+    /// under the running index.
+    pub(crate) fn reload(&self) -> Result<(), VectorDBError> {
+        let graph = Self::load_graph(&self.conn.read())?;
+        *self.graph.write() = graph;
+        self.vector_cache.write().clear();
+        Ok(())
+    }
+
+    fn load_graph(conn: &Connection) -> Result<Graph, VectorDBError> {
+        let mut graph = Graph::empty();
+
+        let entry_point_value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM hnsw_meta WHERE key = 'entry_point'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        if let Some((id, level)) = entry_point_value
+            .and_then(|value| value.split_once(':').map(|(id, l)| (id.to_string(), l.parse().unwrap_or(0))))
+        {
+            graph.entry_point = Some((id, level));
+        }
+
+        let mut stmt = conn.prepare("SELECT node_id, level, neighbor_id FROM hnsw_edges")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)? as usize,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (node_id, level, neighbor_id) = row?;
+            graph.ensure_level(level);
+            graph.levels[level].entry(node_id).or_default().push(neighbor_id);
+        }
+
+        Ok(graph)
+    }
+
+    /// Assign a random maximum layer to a newly inserted node, following the
+    /// geometric distribution `floor(-ln(U(0,1)) * mL)` with `mL = 1 / ln(m)`.
+    fn random_level(&self) -> usize {
+        let m_l = 1.0 / (self.config.m as f64).ln();
+        let u: f64 = rand::random::<f64>().max(f64::EPSILON);
+        (-u.ln() * m_l).floor() as usize
+    }
+
+    /// Insert `id` (with embedding `vector`) into the graph.
+    pub(crate) fn insert(&self, id: &str, vector: &Vector) -> Result<(), VectorDBError> {
+        self.vector_cache.write().insert(id.to_string(), vector.clone());
+
+        let level = self.random_level();
+        let mut graph = self.graph.write();
+        graph.ensure_level(level);
+
+        let entry = graph.entry_point.clone();
+
+        let Some((entry_id, entry_level)) = entry else {
+            // First node in the index: it becomes the sole entry point with no neighbors.
+            for l in 0..=level {
+                graph.levels[l].entry(id.to_string()).or_default();
+            }
+            graph.entry_point = Some((id.to_string(), level));
+            self.persist_entry_point(&self.conn.read(), id, level)?;
+            return Ok(());
+        };
+
+        let conn = self.conn.read();
+        let mut cur = entry_id;
+
+        // Greedily descend from the top layer to `level + 1`, keeping only the
+        // single closest node found at each layer as the next entry point.
+        for l in (level + 1..=entry_level).rev() {
+            cur = self.greedy_closest(&conn, &graph, l, &cur, vector)?;
+        }
+
+        // From `level` down to 0, run a bounded beam search and link the new
+        // node to its pruned nearest neighbors.
+        for l in (0..=level.min(entry_level)).rev() {
+            let candidates = self.search_layer(&conn, &graph, l, &cur, vector, self.config.ef_construction)?;
+            let m = if l == 0 { self.config.m_max } else { self.config.m };
+            let neighbors = self.select_neighbors(&conn, &candidates, m)?;
+
+            graph.ensure_level(l);
+            graph.levels[l].insert(id.to_string(), neighbors.clone());
+            self.persist_neighbors(&conn, id, l, &neighbors)?;
+            for neighbor in &neighbors {
+                let back = graph.levels[l].entry(neighbor.clone()).or_default();
+                if !back.contains(&id.to_string()) {
+                    back.push(id.to_string());
+                    self.persist_edge(&conn, neighbor, l, id)?;
+                }
+                self.prune_neighbors(&conn, &mut graph, l, neighbor, m)?;
+            }
+
+            if let Some((closest, _)) = candidates.first() {
+                cur = closest.clone();
+            }
+        }
+
+        if level > entry_level {
+            graph.entry_point = Some((id.to_string(), level));
+            self.persist_entry_point(&conn, id, level)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove `id` from the graph: unlink it from every neighbor at every
+    /// level (pruning can leave edges asymmetric, so both directions are
+    /// swept), delete its persisted edges, and pick a new entry point if `id`
+    /// held that role.
+    pub(crate) fn remove(&self, id: &str) -> Result<(), VectorDBError> {
+        let mut graph = self.graph.write();
+
+        for level in 0..graph.levels.len() {
+            graph.levels[level].remove(id);
+            for neighbors in graph.levels[level].values_mut() {
+                neighbors.retain(|neighbor| neighbor != id);
+            }
+        }
+
+        let conn = self.conn.read();
+        self.delete_node_edges(&conn, id)?;
+
+        let was_entry_point = graph
+            .entry_point
+            .as_ref()
+            .is_some_and(|(entry_id, _)| entry_id == id);
+        if was_entry_point {
+            graph.entry_point = graph
+                .levels
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(level, nodes)| nodes.keys().next().map(|node_id| (node_id.clone(), level)));
+            match &graph.entry_point {
+                Some((new_id, new_level)) => self.persist_entry_point(&conn, new_id, *new_level)?,
+                None => self.clear_entry_point(&conn)?,
+            }
+        }
+
+        self.vector_cache.write().remove(id);
+        Ok(())
+    }
+
+    /// Drop the entire graph, in memory and on disk.
+    pub(crate) fn clear(&self) -> Result<(), VectorDBError> {
+        *self.graph.write() = Graph::empty();
+        self.vector_cache.write().clear();
+        let conn = self.conn.read();
+        conn.execute_batch("DELETE FROM hnsw_edges; DELETE FROM hnsw_meta;")?;
+        Ok(())
+    }
+
+    /// Query the index for the `k` approximate nearest neighbors of `query`.
+    pub(crate) fn search(&self, query: &Vector, k: usize) -> Result<Vec<(String, f32)>, VectorDBError> {
+        let graph = self.graph.read();
+        let Some((entry_id, entry_level)) = graph.entry_point.clone() else {
+            return Ok(Vec::new());
+        };
+
+        let conn = self.conn.read();
+        let mut cur = entry_id;
+        for l in (1..=entry_level).rev() {
+            cur = self.greedy_closest(&conn, &graph, l, &cur, query)?;
+        }
+
+        let ef = self.config.ef_search.max(k);
+        let mut candidates = self.search_layer(&conn, &graph, 0, &cur, query, ef)?;
+        candidates.truncate(k);
+
+        candidates
+            .into_iter()
+            .map(|(id, dist)| Ok((id, 1.0 - dist)))
+            .collect()
+    }
+
+    /// Greedily walk layer `l` from `start`, returning the single closest node
+    /// found (used to descend between layers before the bounded beam search).
+    fn greedy_closest(
+        &self,
+        conn: &Connection,
+        graph: &Graph,
+        level: usize,
+        start: &str,
+        query: &Vector,
+    ) -> Result<String, VectorDBError> {
+        let results = self.search_layer(conn, graph, level, start, query, 1)?;
+        Ok(results.into_iter().next().map(|(id, _)| id).unwrap_or_else(|| start.to_string()))
+    }
+
+    /// Best-first search of layer `level` starting from `entry`, maintaining a
+    /// dynamic candidate list of size `ef`. Returns `(id, distance)` pairs
+    /// sorted closest-first, where distance is `1 - cosine_similarity`.
+    fn search_layer(
+        &self,
+        conn: &Connection,
+        graph: &Graph,
+        level: usize,
+        entry: &str,
+        query: &Vector,
+        ef: usize,
+    ) -> Result<Vec<(String, f32)>, VectorDBError> {
+        let mut visited: HashSet<String> = HashSet::new();
+        visited.insert(entry.to_string());
+
+        let entry_dist = self.distance(conn, entry, query)?;
+        let mut candidates = vec![(entry.to_string(), entry_dist)];
+        let mut best = candidates.clone();
+
+        while let Some((current, current_dist)) = candidates.pop() {
+            if let Some((_, worst)) = best.last() {
+                if best.len() >= ef && current_dist > *worst {
+                    break;
+                }
+            }
+
+            let neighbors = graph
+                .levels
+                .get(level)
+                .and_then(|level_map| level_map.get(&current))
+                .cloned()
+                .unwrap_or_default();
+
+            for neighbor in neighbors {
+                if !visited.insert(neighbor.clone()) {
+                    continue;
+                }
+                let dist = self.distance(conn, &neighbor, query)?;
+                candidates.push((neighbor.clone(), dist));
+                best.push((neighbor, dist));
+            }
+
+            candidates.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            best.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+            best.truncate(ef);
+        }
+
+        Ok(best)
+    }
+
+    /// Prune `candidates` to at most `m` neighbors, keeping a neighbor only if
+    /// it is closer to the new node than to any neighbor already selected.
+    fn select_neighbors(
+        &self,
+        conn: &Connection,
+        candidates: &[(String, f32)],
+        m: usize,
+    ) -> Result<Vec<String>, VectorDBError> {
+        let mut sorted = candidates.to_vec();
+        sorted.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut selected: Vec<(String, Vector)> = Vec::new();
+        for (id, dist_to_query) in sorted {
+            if selected.len() >= m {
+                break;
+            }
+            let candidate_vector = self.fetch_vector(conn, &id)?;
+            let closer_to_existing = selected.iter().any(|(_, existing)| {
+                let dist_to_existing = 1.0 - candidate_vector.cosine_similarity(existing);
+                dist_to_existing < dist_to_query
+            });
+            if !closer_to_existing {
+                selected.push((id, candidate_vector));
+            }
+        }
+
+        Ok(selected.into_iter().map(|(id, _)| id).collect())
+    }
+
+    fn prune_neighbors(
+        &self,
+        conn: &Connection,
+        graph: &mut Graph,
+        level: usize,
+        id: &str,
+        m: usize,
+    ) -> Result<(), VectorDBError> {
+        let current = graph
+            .levels
+            .get(level)
+            .and_then(|l| l.get(id))
+            .cloned()
+            .unwrap_or_default();
+        if current.len() <= m {
+            return Ok(());
+        }
+
+        let vector = self.fetch_vector(conn, id)?;
+        let scored: Vec<(String, f32)> = current
+            .iter()
+            .map(|n| Ok((n.clone(), 1.0 - self.fetch_vector(conn, n)?.cosine_similarity(&vector))))
+            .collect::<Result<_, VectorDBError>>()?;
+        let pruned = self.select_neighbors(conn, &scored, m)?;
+        graph.levels[level].insert(id.to_string(), pruned.clone());
+        self.persist_neighbors(conn, id, level, &pruned)?;
+        Ok(())
+    }
+
+    fn distance(&self, conn: &Connection, id: &str, query: &Vector) -> Result<f32, VectorDBError> {
+        let vector = self.fetch_vector(conn, id)?;
+        Ok(1.0 - vector.cosine_similarity(&query))
+    }
+
+    fn fetch_vector(&self, conn: &Connection, id: &str) -> Result<Vector, VectorDBError> {
+        if let Some(vector) = self.vector_cache.read().get(id) {
+            return Ok(vector.clone());
+        }
+
+        let bytes: Vec<u8> = conn.query_row(
+            "SELECT embedding FROM vectors WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        let vector = crate::quantize::StoredEmbedding::from_bytes(&bytes)?.decode();
+        self.vector_cache.write().insert(id.to_string(), vector.clone());
+        Ok(vector)
+    }
+
+    /// Persist a single edge. Takes an already-held `conn` rather than locking
+    /// internally: every call site runs while the caller's own `self.conn.read()`
+    /// guard is still alive, and `parking_lot::RwLock` does not support recursive
+    /// read acquisition against a queued writer without risking deadlock.
+    fn persist_edge(&self, conn: &Connection, node_id: &str, level: usize, neighbor_id: &str) -> Result<(), VectorDBError> {
+        conn.execute(
+            "INSERT OR IGNORE INTO hnsw_edges (node_id, level, neighbor_id) VALUES (?1, ?2, ?3)",
+            params![node_id, level as i64, neighbor_id],
+        )?;
+        Ok(())
+    }
+
+    /// Replace the full persisted neighbor set of `node_id` at `level` with
+    /// exactly `neighbors`, so pruning on either side of an edge is reflected
+    /// in `hnsw_edges` rather than leaving stale rows behind. Takes an
+    /// already-held `conn`; see `persist_edge` for why.
+    fn persist_neighbors(&self, conn: &Connection, node_id: &str, level: usize, neighbors: &[String]) -> Result<(), VectorDBError> {
+        conn.execute(
+            "DELETE FROM hnsw_edges WHERE node_id = ?1 AND level = ?2",
+            params![node_id, level as i64],
+        )?;
+        for neighbor_id in neighbors {
+            conn.execute(
+                "INSERT OR IGNORE INTO hnsw_edges (node_id, level, neighbor_id) VALUES (?1, ?2, ?3)",
+                params![node_id, level as i64, neighbor_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Takes an already-held `conn`; see `persist_edge` for why.
+    fn persist_entry_point(&self, conn: &Connection, id: &str, level: usize) -> Result<(), VectorDBError> {
+        conn.execute(
+            "INSERT OR REPLACE INTO hnsw_meta (key, value) VALUES ('entry_point', ?1)",
+            params![format!("{}:{}", id, level)],
+        )?;
+        Ok(())
+    }
+
+    fn clear_entry_point(&self, conn: &Connection) -> Result<(), VectorDBError> {
+        conn.execute("DELETE FROM hnsw_meta WHERE key = 'entry_point'", [])?;
+        Ok(())
+    }
+
+    /// Delete every persisted edge touching `id`, as either endpoint. Takes an
+    /// already-held `conn`; see `persist_edge` for why.
+    fn delete_node_edges(&self, conn: &Connection, id: &str) -> Result<(), VectorDBError> {
+        conn.execute(
+            "DELETE FROM hnsw_edges WHERE node_id = ?1 OR neighbor_id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+}
@@ -37,19 +37,34 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(missing_docs, rust_2018_idioms)]
 
+use std::collections::HashMap;
+use std::hash::Hasher;
 use std::path::Path;
 use std::sync::Arc;
+#[cfg(feature = "quic-sync")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(feature = "hnsw")]
+use rusqlite::OptionalExtension;
 #[cfg(feature = "simd")]
 use wide::f32x8;
 
+#[cfg(feature = "hnsw")]
+pub mod hnsw;
+pub mod quantize;
+pub mod query;
 #[cfg(feature = "quic-sync")]
 pub mod sync;
+#[cfg(feature = "vtab")]
+pub mod vtab;
+
+pub use quantize::Quantization;
 
 /// Vector type for embeddings
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -99,6 +114,30 @@ pub struct Config {
     pub cache_size: usize,
     /// Synchronous mode (0=OFF, 1=NORMAL, 2=FULL)
     pub synchronous: u8,
+    /// Maximum number of pending writes `insert_batch` accumulates before an
+    /// automatic flush.
+    pub batch_max_items: usize,
+    /// Maximum total size in bytes (summed embedding + metadata) of pending
+    /// writes `insert_batch` accumulates before an automatic flush.
+    pub batch_max_bytes: usize,
+    /// Age in milliseconds of the batch queue, measured from when it went
+    /// from empty to non-empty, at which the next `insert_batch` call flushes
+    /// it. The age is checked only when a write is queued; nothing flushes
+    /// the queue in the background.
+    pub batch_flush_ms: u64,
+    /// Maximum number of entries the `insert_batch` dedup cache retains
+    /// before it is reset. Bounds the cache's memory for databases with many
+    /// distinct ids; a reset only means the next re-send of an already
+    /// flushed embedding is re-written rather than skipped.
+    pub dedup_cache_limit: usize,
+    /// Embedding storage mode. Defaults to [`Quantization::None`] (full,
+    /// exact `f32`); [`Quantization::Int8`] trades a small, bounded recall
+    /// cost for roughly a quarter of the storage and a faster scan.
+    pub quantization: Quantization,
+    /// HNSW index configuration. `None` (the default) disables the index and
+    /// `search` falls back to an exact brute-force scan.
+    #[cfg(feature = "hnsw")]
+    pub hnsw: Option<hnsw::Config>,
 }
 
 impl Default for Config {
@@ -108,6 +147,13 @@ impl Default for Config {
             wal_mode: true,
             cache_size: 2000,
             synchronous: 1, // NORMAL
+            batch_max_items: 256,
+            batch_max_bytes: 1_000_000,
+            batch_flush_ms: 50,
+            dedup_cache_limit: 100_000,
+            quantization: Quantization::None,
+            #[cfg(feature = "hnsw")]
+            hnsw: None,
         }
     }
 }
@@ -123,6 +169,32 @@ pub struct SearchResult {
     pub metadata: String,
 }
 
+/// A mutation observed on a [`VectorDB`], delivered to observers registered
+/// via [`VectorDB::add_observer`] only once the transaction it belongs to has
+/// durably committed.
+#[derive(Debug, Clone)]
+pub enum ChangeEvent {
+    /// A row was inserted or replaced.
+    Inserted {
+        /// The affected document id.
+        id: String,
+    },
+    /// A row was deleted.
+    Deleted {
+        /// The affected document id.
+        id: String,
+    },
+    /// All rows were removed via [`VectorDB::clear`].
+    Cleared,
+    /// The entire database was overwritten in place by [`VectorDB::restore`]
+    /// or [`VectorDB::restore_with_progress`]. Unlike `Cleared`, the table is
+    /// not necessarily empty afterwards — treat this as "rebuild any derived
+    /// state from scratch" rather than "everything is gone".
+    Restored,
+}
+
+type Observer = Box<dyn Fn(ChangeEvent) + Send + Sync>;
+
 /// Vector database errors
 #[derive(Error, Debug)]
 pub enum VectorDBError {
@@ -147,6 +219,42 @@ pub enum VectorDBError {
 pub struct VectorDB {
     conn: Arc<RwLock<Connection>>,
     dimension: Option<usize>,
+    config: Config,
+    queue: Mutex<BatchQueue>,
+    observers: Arc<Mutex<Vec<Observer>>>,
+    pending_events: Arc<Mutex<Vec<ChangeEvent>>>,
+    #[cfg(feature = "quic-sync")]
+    session: Mutex<Option<sync::ActiveSession>>,
+    /// Monotonic counter stamped onto every local write's `lamport` column,
+    /// so [`sync::lamport_wins`] can compare incoming changes against a real
+    /// version number instead of wall-clock time.
+    #[cfg(feature = "quic-sync")]
+    lamport: AtomicU64,
+    #[cfg(feature = "hnsw")]
+    hnsw: Option<Arc<hnsw::HnswIndex>>,
+}
+
+/// A pending write accumulated by [`VectorDB::insert_batch`].
+struct PendingInsert {
+    id: String,
+    embedding_bytes: Vec<u8>,
+    metadata: String,
+}
+
+/// In-memory batch of writes awaiting a flush, plus the dedup cache keyed by
+/// a hash of each id's most recently queued or flushed embedding.
+#[derive(Default)]
+struct BatchQueue {
+    items: Vec<PendingInsert>,
+    bytes: usize,
+    started_at: Option<Instant>,
+    dedup: HashMap<String, u64>,
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
 }
 
 impl VectorDB {
@@ -174,7 +282,24 @@ impl VectorDB {
         conn.execute_batch(&format!("PRAGMA synchronous={};", config.synchronous))?;
         conn.execute_batch("PRAGMA temp_store=MEMORY;")?;
 
-        // Create schema
+        // Create schema. `lamport` only exists under `quic-sync`: it's a
+        // per-row version counter used by last-writer-wins conflict
+        // resolution, distinct from `created_at` (wall-clock, for ordering
+        // queries) which doesn't survive comparison across peers with
+        // different clocks.
+        #[cfg(feature = "quic-sync")]
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS vectors (
+                id TEXT PRIMARY KEY,
+                embedding BLOB NOT NULL,
+                metadata TEXT,
+                created_at INTEGER DEFAULT (unixepoch()),
+                lamport INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_created_at ON vectors(created_at);
+            "
+        )?;
+        #[cfg(not(feature = "quic-sync"))]
         conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS vectors (
                 id TEXT PRIMARY KEY,
@@ -186,12 +311,129 @@ impl VectorDB {
             "
         )?;
 
+        let observers: Arc<Mutex<Vec<Observer>>> = Arc::new(Mutex::new(Vec::new()));
+        let pending_events: Arc<Mutex<Vec<ChangeEvent>>> = Arc::new(Mutex::new(Vec::new()));
+
+        // Fire observers only once a transaction has durably committed, so
+        // batched writes (see `insert_batch`/`flush`) report each row exactly
+        // once rather than per intermediate statement.
+        {
+            let observers = observers.clone();
+            let pending_events = pending_events.clone();
+            conn.commit_hook(Some(move || {
+                let events = std::mem::take(&mut *pending_events.lock());
+                if !events.is_empty() {
+                    let observers = observers.lock();
+                    for event in events {
+                        for observer in observers.iter() {
+                            observer(event.clone());
+                        }
+                    }
+                }
+                false
+            }));
+        }
+
+        let conn = Arc::new(RwLock::new(conn));
+
+        #[cfg(feature = "hnsw")]
+        let hnsw = config
+            .hnsw
+            .clone()
+            .map(|hnsw_config| hnsw::HnswIndex::open(conn.clone(), hnsw_config).map(Arc::new))
+            .transpose()?;
+
         Ok(Self {
-            conn: Arc::new(RwLock::new(conn)),
+            conn,
             dimension: None,
+            config,
+            queue: Mutex::new(BatchQueue::default()),
+            observers,
+            pending_events,
+            #[cfg(feature = "quic-sync")]
+            session: Mutex::new(None),
+            #[cfg(feature = "quic-sync")]
+            lamport: AtomicU64::new(0),
+            #[cfg(feature = "hnsw")]
+            hnsw,
         })
     }
 
+    /// Allocate the next `lamport` value for a local write.
+    #[cfg(feature = "quic-sync")]
+    fn next_lamport(&self) -> u64 {
+        self.lamport.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Register an observer invoked with a [`ChangeEvent`] for every mutation
+    /// made through `insert`, `insert_batch`/`flush`, `delete`, `clear`, or
+    /// `restore`. For the first four, it fires once the enclosing transaction
+    /// commits durably to disk. Rows written by [`VectorDB::apply_changeset`] produce no event.
+    ///
+    /// This is the integration point for the QUIC sync layer (enqueueing a
+    /// changeset), an external ANN index that must stay current, or
+    /// user-side cache invalidation.
+    pub fn add_observer<F>(&self, observer: F)
+    where
+        F: Fn(ChangeEvent) + Send + Sync + 'static,
+    {
+        self.observers.lock().push(Box::new(observer));
+    }
+
+    /// Begin accumulating a changeset for the `vectors` table.
+    ///
+    /// Attaches a SQLite session to the table so that subsequent `insert`,
+    /// `delete`, and `clear` calls are captured; call [`VectorDB::take_changeset`]
+    /// to collect them as a [`sync::SyncMessage::Changeset`] payload. Starting a
+    /// new session replaces (and discards the changes of) any session already
+    /// in progress.
+    #[cfg(feature = "quic-sync")]
+    pub fn begin_session(&self) -> Result<(), VectorDBError> {
+        let mut session = self.session.lock();
+        // A session in progress holds the connection's write lock; end it
+        // before the new one acquires that lock.
+        drop(session.take());
+        *session = Some(sync::begin_session(self.conn.clone())?);
+        Ok(())
+    }
+
+    /// Drain the changeset accumulated since the last [`VectorDB::begin_session`],
+    /// ending the session. Returns `None` if no session is active or nothing
+    /// changed.
+    #[cfg(feature = "quic-sync")]
+    pub fn take_changeset(&self) -> Result<Option<Vec<u8>>, VectorDBError> {
+        match self.session.lock().take() {
+            Some(active) => sync::take_changeset(active),
+            None => Ok(None),
+        }
+    }
+
+    /// Apply a changeset captured by a peer's [`VectorDB::take_changeset`] to
+    /// this database, resolving any conflicting rows with last-writer-wins
+    /// semantics against `lamport` (the peer's counter at the time the
+    /// changeset was taken — see [`sync::SyncMessage::Changeset`]).
+    #[cfg(feature = "quic-sync")]
+    pub fn apply_changeset(&self, bytes: &[u8], lamport: u64) -> Result<(), VectorDBError> {
+        let conn = self.conn.write();
+        sync::apply_changeset(&conn, bytes, lamport)
+    }
+
+    /// Acquire mutable access to the connection for a single mutation. When a
+    /// sync session is active, reuses the write lock it already holds instead
+    /// of taking a second, independent one on `self.conn` — see
+    /// [`sync::ActiveSession`] for why the two must never coexist.
+    fn with_write_conn<R>(&self, f: impl FnOnce(&mut Connection) -> Result<R, VectorDBError>) -> Result<R, VectorDBError> {
+        #[cfg(feature = "quic-sync")]
+        {
+            let mut session = self.session.lock();
+            if let Some(active) = session.as_mut() {
+                return f(active.connection_mut());
+            }
+        }
+        let mut conn = self.conn.write();
+        f(&mut conn)
+    }
+
     /// Insert a vector into the database
     ///
     /// # Arguments
@@ -209,19 +451,169 @@ impl VectorDB {
             }
         }
 
-        let embedding_bytes = rmp_serde::to_vec(&vector)
-            .map_err(|e| VectorDBError::Serialization(e.to_string()))?;
+        let embedding_bytes = quantize::StoredEmbedding::encode(&vector, self.config.quantization).to_bytes()?;
+
+        self.with_write_conn(|conn| {
+            let tx = conn.transaction()?;
+            #[cfg(feature = "quic-sync")]
+            tx.execute(
+                "INSERT OR REPLACE INTO vectors (id, embedding, metadata, lamport) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![id, embedding_bytes, metadata, self.next_lamport()],
+            )?;
+            #[cfg(not(feature = "quic-sync"))]
+            tx.execute(
+                "INSERT OR REPLACE INTO vectors (id, embedding, metadata) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id, embedding_bytes, metadata],
+            )?;
+            // Only queue the event once the statement itself has succeeded, and
+            // undo it if the commit that fires the commit hook fails, so a
+            // failed write never produces a phantom notification on the next
+            // successful commit.
+            self.pending_events.lock().push(ChangeEvent::Inserted { id: id.to_string() });
+            if let Err(err) = tx.commit() {
+                self.pending_events.lock().pop();
+                return Err(err.into());
+            }
+            Ok(())
+        })?;
+        // A direct insert writes `id` outside the batch queue entirely, so any
+        // dedup entry recorded for it by a previous `insert_batch` no longer
+        // reflects what's on disk; drop it the same way `delete` does, or a
+        // later re-send of that stale embedding would be skipped as a no-op.
+        self.queue.lock().dedup.remove(id);
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            hnsw.insert(id, &vector)?;
+        }
 
-        let conn = self.conn.write();
-        conn.execute(
-            "INSERT OR REPLACE INTO vectors (id, embedding, metadata) VALUES (?1, ?2, ?3)",
-            rusqlite::params![id, embedding_bytes, metadata],
-        )?;
+        Ok(())
+    }
 
+    /// Queue a batch of vectors for insertion, flushing automatically once
+    /// [`Config::batch_max_items`] items or [`Config::batch_max_bytes`] bytes
+    /// are pending, or once [`Config::batch_flush_ms`] has elapsed since the
+    /// queue went from empty to non-empty. These limits are checked only as
+    /// each write is queued, so writes left pending after the last call stay
+    /// queued until [`VectorDB::flush`] forces a flush of whatever remains
+    /// (e.g. at the end of a bulk-ingest run).
+    ///
+    /// Re-queuing an identical embedding under an id already pending or
+    /// already flushed is a no-op, tracked via a dedup cache keyed by a hash
+    /// of the embedding bytes.
+    pub fn insert_batch(&self, items: Vec<(String, Vector, String)>) -> Result<(), VectorDBError> {
+        for (id, vector, metadata) in items {
+            self.enqueue(id, vector, &metadata)?;
+        }
         Ok(())
     }
 
-    /// Search for similar vectors using cosine similarity
+    fn enqueue(&self, id: String, vector: Vector, metadata: &str) -> Result<(), VectorDBError> {
+        if let Some(dim) = self.dimension {
+            if vector.dim() != dim {
+                return Err(VectorDBError::InvalidDimension {
+                    expected: dim,
+                    got: vector.dim(),
+                });
+            }
+        }
+
+        let embedding_bytes = quantize::StoredEmbedding::encode(&vector, self.config.quantization).to_bytes()?;
+        let hash = hash_bytes(&embedding_bytes);
+
+        let mut queue = self.queue.lock();
+        if queue.dedup.get(&id) == Some(&hash) {
+            return Ok(());
+        }
+        if queue.dedup.len() >= self.config.dedup_cache_limit {
+            queue.dedup.clear();
+        }
+        queue.dedup.insert(id.clone(), hash);
+
+        if queue.items.is_empty() {
+            queue.started_at = Some(Instant::now());
+        }
+        queue.bytes += embedding_bytes.len() + metadata.len();
+        queue.items.push(PendingInsert {
+            id,
+            embedding_bytes,
+            metadata: metadata.to_string(),
+        });
+
+        let should_flush = queue.items.len() >= self.config.batch_max_items
+            || queue.bytes >= self.config.batch_max_bytes
+            || queue
+                .started_at
+                .is_some_and(|t| t.elapsed().as_millis() as u64 >= self.config.batch_flush_ms);
+
+        if should_flush {
+            self.flush_locked(&mut queue)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush any writes pending in the batch queue in a single transaction.
+    /// Returns the number of rows written.
+    pub fn flush(&self) -> Result<usize, VectorDBError> {
+        let mut queue = self.queue.lock();
+        self.flush_locked(&mut queue)
+    }
+
+    fn flush_locked(&self, queue: &mut BatchQueue) -> Result<usize, VectorDBError> {
+        if queue.items.is_empty() {
+            return Ok(0);
+        }
+
+        let items = std::mem::take(&mut queue.items);
+        queue.bytes = 0;
+        queue.started_at = None;
+
+        self.with_write_conn(|conn| {
+            let tx = conn.transaction()?;
+            for item in &items {
+                #[cfg(feature = "quic-sync")]
+                tx.execute(
+                    "INSERT OR REPLACE INTO vectors (id, embedding, metadata, lamport) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![item.id, item.embedding_bytes, item.metadata, self.next_lamport()],
+                )?;
+                #[cfg(not(feature = "quic-sync"))]
+                tx.execute(
+                    "INSERT OR REPLACE INTO vectors (id, embedding, metadata) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![item.id, item.embedding_bytes, item.metadata],
+                )?;
+            }
+            // Only queue events once every statement in the batch has
+            // succeeded, and undo them if the commit fails, so a failed flush
+            // never produces phantom notifications on the next one.
+            self.pending_events
+                .lock()
+                .extend(items.iter().map(|item| ChangeEvent::Inserted { id: item.id.clone() }));
+            if let Err(err) = tx.commit() {
+                let mut pending = self.pending_events.lock();
+                let truncated = pending.len().saturating_sub(items.len());
+                pending.truncate(truncated);
+                return Err(err.into());
+            }
+            Ok(())
+        })?;
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            for item in &items {
+                let vector = quantize::StoredEmbedding::from_bytes(&item.embedding_bytes)?.decode();
+                hnsw.insert(&item.id, &vector)?;
+            }
+        }
+
+        Ok(items.len())
+    }
+
+    /// Search for similar vectors.
+    ///
+    /// Uses the HNSW approximate index when one is configured (see
+    /// [`Config::hnsw`]), falling back to an exact brute-force scan otherwise.
+    /// Use [`VectorDB::search_exact`] directly if you always want the latter.
     ///
     /// # Arguments
     /// * `query` - Query vector
@@ -230,11 +622,66 @@ impl VectorDB {
     /// # Returns
     /// Vector of search results sorted by similarity score (descending)
     pub fn search(&self, query: &Vector, k: usize) -> Result<Vec<SearchResult>, VectorDBError> {
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            let hits = hnsw.search(query, k)?;
+            let conn = self.conn.read();
+            // A hit can lag a concurrent delete/clear that hasn't pruned the
+            // graph yet; skip ids no longer in `vectors` instead of erroring.
+            return hits
+                .into_iter()
+                .filter_map(|(id, score)| {
+                    let metadata: Option<String> = match conn
+                        .query_row("SELECT metadata FROM vectors WHERE id = ?1", rusqlite::params![id], |row| row.get(0))
+                        .optional()
+                    {
+                        Ok(metadata) => metadata,
+                        Err(e) => return Some(Err(e.into())),
+                    };
+                    metadata.map(|metadata| Ok(SearchResult { id, score, metadata }))
+                })
+                .collect();
+        }
+
+        self.search_exact(query, k)
+    }
+
+    /// Search for similar vectors using an exact, full-table brute-force scan,
+    /// regardless of whether an HNSW index is configured.
+    ///
+    /// # Arguments
+    /// * `query` - Query vector
+    /// * `k` - Number of results to return
+    ///
+    /// # Returns
+    /// Vector of search results sorted by similarity score (descending)
+    pub fn search_exact(&self, query: &Vector, k: usize) -> Result<Vec<SearchResult>, VectorDBError> {
+        self.search_filtered(query, k, "", &[])
+    }
+
+    /// Start building a metadata-filtered similarity search over the JSON
+    /// `metadata` column. See [`query::SearchQuery`].
+    pub fn query(&self) -> query::SearchQuery<'_> {
+        query::SearchQuery::new(self)
+    }
+
+    /// Run a brute-force k-nearest-neighbor scan restricted to rows matching
+    /// `where_clause` (e.g. `"WHERE json_extract(metadata, '$.category') = ?1"`),
+    /// bound to `params`. Used by [`VectorDB::search_exact`] (with an empty
+    /// filter) and [`query::SearchQuery::knn`].
+    pub(crate) fn search_filtered(
+        &self,
+        query: &Vector,
+        k: usize,
+        where_clause: &str,
+        params: &[rusqlite::types::Value],
+    ) -> Result<Vec<SearchResult>, VectorDBError> {
         let conn = self.conn.read();
-        let mut stmt = conn.prepare("SELECT id, embedding, metadata FROM vectors")?;
+        let sql = format!("SELECT id, embedding, metadata FROM vectors {}", where_clause);
+        let mut stmt = conn.prepare(&sql)?;
 
         let mut results: Vec<SearchResult> = stmt
-            .query_map([], |row| {
+            .query_map(rusqlite::params_from_iter(params), |row| {
                 let id: String = row.get(0)?;
                 let embedding_bytes: Vec<u8> = row.get(1)?;
                 let metadata: String = row.get(2)?;
@@ -243,8 +690,8 @@ impl VectorDB {
             })?
             .filter_map(|r| r.ok())
             .filter_map(|(id, embedding_bytes, metadata)| {
-                let vector: Vector = rmp_serde::from_slice(&embedding_bytes).ok()?;
-                let score = query.cosine_similarity(&vector);
+                let stored = quantize::StoredEmbedding::from_bytes(&embedding_bytes).ok()?;
+                let score = stored.cosine_similarity(query);
 
                 Some(SearchResult {
                     id,
@@ -263,8 +710,25 @@ impl VectorDB {
 
     /// Delete a vector by ID
     pub fn delete(&self, id: &str) -> Result<(), VectorDBError> {
-        let conn = self.conn.write();
-        conn.execute("DELETE FROM vectors WHERE id = ?1", rusqlite::params![id])?;
+        self.with_write_conn(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM vectors WHERE id = ?1", rusqlite::params![id])?;
+            self.pending_events.lock().push(ChangeEvent::Deleted { id: id.to_string() });
+            if let Err(err) = tx.commit() {
+                self.pending_events.lock().pop();
+                return Err(err.into());
+            }
+            Ok(())
+        })?;
+        // Invalidate the dedup cache entry so a later `insert_batch` re-sending
+        // the same id/embedding is written instead of being skipped as a no-op.
+        self.queue.lock().dedup.remove(id);
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            hnsw.remove(id)?;
+        }
+
         Ok(())
     }
 
@@ -277,8 +741,23 @@ impl VectorDB {
 
     /// Clear all vectors from the database
     pub fn clear(&self) -> Result<(), VectorDBError> {
-        let conn = self.conn.write();
-        conn.execute("DELETE FROM vectors", [])?;
+        self.with_write_conn(|conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM vectors", [])?;
+            self.pending_events.lock().push(ChangeEvent::Cleared);
+            if let Err(err) = tx.commit() {
+                self.pending_events.lock().pop();
+                return Err(err.into());
+            }
+            Ok(())
+        })?;
+        self.queue.lock().dedup.clear();
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            hnsw.clear()?;
+        }
+
         Ok(())
     }
 
@@ -297,14 +776,210 @@ impl VectorDB {
 
         match result {
             Ok((embedding_bytes, metadata)) => {
-                let vector: Vector = rmp_serde::from_slice(&embedding_bytes)
-                    .map_err(|e| VectorDBError::Serialization(e.to_string()))?;
+                let vector = quantize::StoredEmbedding::from_bytes(&embedding_bytes)?.decode();
                 Ok(Some((vector, metadata)))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
             Err(e) => Err(e.into()),
         }
     }
+
+    /// Register the `vec_search` virtual table (see [`vtab`]) on this
+    /// database's connection, so similarity search can be expressed in SQL:
+    ///
+    /// ```sql
+    /// SELECT id, metadata, distance FROM vec_search WHERE query = ?1 AND k = 10
+    /// ```
+    ///
+    /// Scans through the HNSW index when one is configured, falling back to
+    /// the brute-force scan otherwise — the same dispatch [`VectorDB::search`]
+    /// uses.
+    #[cfg(feature = "vtab")]
+    pub fn register_vec_search(&self) -> Result<(), VectorDBError> {
+        let scan = self.vec_search_scan_fn();
+        let conn = self.conn.write();
+        vtab::register(
+            &conn,
+            vtab::VecSearchAux {
+                conn: self.conn.clone(),
+                scan,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Run a read-only SQL query against this database's connection, calling
+    /// `row_fn` once per matching row. This is the query surface for
+    /// [`VectorDB::register_vec_search`]'s `vec_search` table (e.g.
+    /// `SELECT id, metadata, distance FROM vec_search WHERE query = ?1 AND k = ?2`,
+    /// optionally joined against the caller's own tables), since `vec_search`
+    /// is otherwise unreachable from outside this crate.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use sqlite_vector::{VectorDB, Config, Vector};
+    /// # fn run() -> Result<(), sqlite_vector::VectorDBError> {
+    /// # let db = VectorDB::new("vectors.db", Config::default())?;
+    /// db.register_vec_search()?;
+    /// let query_vec = Vector::from_slice(&[0.1, 0.2, 0.3, 0.4]);
+    /// let query_bytes = rmp_serde::to_vec(&query_vec).unwrap();
+    /// let ids: Vec<String> = db.query_sql(
+    ///     "SELECT id FROM vec_search WHERE query = ?1 AND k = ?2",
+    ///     rusqlite::params![query_bytes, 10],
+    ///     |row| row.get(0),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "vtab")]
+    pub fn query_sql<P, T, F>(&self, sql: &str, params: P, row_fn: F) -> Result<Vec<T>, VectorDBError>
+    where
+        P: rusqlite::Params,
+        F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+    {
+        let conn = self.conn.read();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, row_fn)?;
+        rows.collect::<rusqlite::Result<Vec<T>>>().map_err(Into::into)
+    }
+
+    #[cfg(all(feature = "vtab", feature = "hnsw"))]
+    fn vec_search_scan_fn(&self) -> Arc<vtab::ScanFn> {
+        match self.hnsw.clone() {
+            Some(hnsw) => Arc::new(move |query: &Vector, k: usize| hnsw.search(query, k)),
+            None => {
+                let conn = self.conn.clone();
+                Arc::new(move |query: &Vector, k: usize| brute_force_scan(&conn, query, k))
+            }
+        }
+    }
+
+    #[cfg(all(feature = "vtab", not(feature = "hnsw")))]
+    fn vec_search_scan_fn(&self) -> Arc<vtab::ScanFn> {
+        let conn = self.conn.clone();
+        Arc::new(move |query: &Vector, k: usize| brute_force_scan(&conn, query, k))
+    }
+
+    /// Take a consistent, hot snapshot of this (possibly WAL-mode, live)
+    /// database into a new file at `dst`, using SQLite's online backup API to
+    /// copy pages incrementally rather than requiring callers to stop writes
+    /// and `fs::copy` the `.db`/`-wal`/`-shm` files themselves.
+    pub fn backup<P: AsRef<Path>>(&self, dst: P) -> Result<(), VectorDBError> {
+        self.backup_with_progress(dst, |_| {})
+    }
+
+    /// Like [`VectorDB::backup`], calling `progress` after each chunk of
+    /// pages is copied.
+    pub fn backup_with_progress<P: AsRef<Path>, F: FnMut(rusqlite::backup::Progress)>(
+        &self,
+        dst: P,
+        mut progress: F,
+    ) -> Result<(), VectorDBError> {
+        let src = self.conn.read();
+        let mut dst_conn = Connection::open(dst)?;
+        let backup = rusqlite::backup::Backup::new(&src, &mut dst_conn)?;
+        run_backup_to_completion(&backup, &mut progress)?;
+        Ok(())
+    }
+
+    /// Restore this database in place from a snapshot at `src` (e.g. one
+    /// produced by [`VectorDB::backup`]), using SQLite's online backup API so
+    /// the live connection's WAL and schema stay consistent throughout. This
+    /// also gives a fresh peer a bootstrap path for catching up before QUIC
+    /// changeset streaming takes over.
+    ///
+    /// Since this overwrites `vectors` (and the HNSW adjacency tables, if
+    /// present) wholesale rather than through `insert`/`delete`/`clear`, it
+    /// also rebuilds the in-memory HNSW graph, drops the `insert_batch` dedup
+    /// cache (its hashes no longer reflect what's on disk), and notifies
+    /// observers with [`ChangeEvent::Restored`] so sync fan-out and external
+    /// caches learn the data changed underneath them.
+    pub fn restore<P: AsRef<Path>>(&self, src: P) -> Result<(), VectorDBError> {
+        self.restore_with_progress(src, |_| {})
+    }
+
+    /// Like [`VectorDB::restore`], calling `progress` after each chunk of
+    /// pages is copied.
+    pub fn restore_with_progress<P: AsRef<Path>, F: FnMut(rusqlite::backup::Progress)>(
+        &self,
+        src: P,
+        mut progress: F,
+    ) -> Result<(), VectorDBError> {
+        let src_conn = Connection::open(src)?;
+        {
+            let mut dst = self.conn.write();
+            let backup = rusqlite::backup::Backup::new(&src_conn, &mut dst)?;
+            run_backup_to_completion(&backup, &mut progress)?;
+        }
+
+        self.queue.lock().dedup.clear();
+
+        #[cfg(feature = "hnsw")]
+        if let Some(hnsw) = &self.hnsw {
+            hnsw.reload()?;
+        }
+
+        for observer in self.observers.lock().iter() {
+            observer(ChangeEvent::Restored);
+        }
+
+        Ok(())
+    }
+}
+
+/// Drive `backup` to completion, calling `progress` after each chunk of pages
+/// is copied.
+///
+/// `Backup::run_to_completion` can't be used directly here: its `progress`
+/// parameter is a plain `fn(Progress)`, not a generic `FnMut`, so it can't
+/// accept a closure that captures state (as `backup_with_progress`'s and
+/// `restore_with_progress`'s callers' closures do). This reimplements its
+/// step/sleep loop, calling `progress` by reference instead.
+fn run_backup_to_completion<F: FnMut(rusqlite::backup::Progress)>(
+    backup: &rusqlite::backup::Backup<'_, '_>,
+    progress: &mut F,
+) -> rusqlite::Result<()> {
+    use rusqlite::backup::StepResult::Done;
+
+    const PAGES_PER_STEP: std::os::raw::c_int = 100;
+    const PAUSE_BETWEEN_PAGES: std::time::Duration = std::time::Duration::from_millis(10);
+
+    loop {
+        let step_result = backup.step(PAGES_PER_STEP)?;
+        progress(backup.progress());
+        match step_result {
+            Done => return Ok(()),
+            // `More`, `Busy`, `Locked`, and any future variant all mean "not
+            // done yet" — give SQLite a moment before stepping again, same as
+            // `run_to_completion` does.
+            _ => std::thread::sleep(PAUSE_BETWEEN_PAGES),
+        }
+    }
+}
+
+/// Brute-force similarity scan used as the `vec_search` virtual table's
+/// fallback when no HNSW index is configured.
+#[cfg(feature = "vtab")]
+fn brute_force_scan(conn: &Arc<RwLock<Connection>>, query: &Vector, k: usize) -> Result<Vec<(String, f32)>, VectorDBError> {
+    let conn = conn.read();
+    let mut stmt = conn.prepare("SELECT id, embedding FROM vectors")?;
+
+    let mut results: Vec<(String, f32)> = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            let embedding_bytes: Vec<u8> = row.get(1)?;
+            Ok((id, embedding_bytes))
+        })?
+        .filter_map(|r| r.ok())
+        .filter_map(|(id, embedding_bytes)| {
+            let stored = quantize::StoredEmbedding::from_bytes(&embedding_bytes).ok()?;
+            Some((id, stored.cosine_similarity(query)))
+        })
+        .collect();
+
+    results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(k);
+    Ok(results)
 }
 
 // SIMD-accelerated cosine similarity using 'wide' crate
@@ -452,4 +1127,191 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_insert_batch_after_delete_is_not_deduped() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default())?;
+
+        let v1 = Vector::from_slice(&[1.0, 2.0, 3.0]);
+        db.insert_batch(vec![("doc1".to_string(), v1.clone(), r#"{}"#.to_string())])?;
+        db.flush()?;
+        assert_eq!(db.count()?, 1);
+
+        db.delete("doc1")?;
+        assert_eq!(db.count()?, 0);
+
+        // Re-inserting the exact same embedding under the same id must not be
+        // skipped as a stale dedup hit now that the row is gone.
+        db.insert_batch(vec![("doc1".to_string(), v1, r#"{}"#.to_string())])?;
+        db.flush()?;
+        assert_eq!(db.count()?, 1);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[test]
+    fn test_search_after_delete_with_hnsw_enabled() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            hnsw: Some(hnsw::Config::default()),
+            ..Config::default()
+        };
+        let db = VectorDB::new(temp_file.path(), config)?;
+
+        for i in 0..20 {
+            let v = Vector::from_slice(&[i as f32, (i * 2) as f32, (i * 3) as f32]);
+            db.insert(&format!("doc{i}"), v, r#"{}"#)?;
+        }
+
+        let query = Vector::from_slice(&[1.0, 2.0, 3.0]);
+        db.delete("doc1")?;
+
+        // A stale hit for a deleted id must not make search error out.
+        let results = db.search(&query, 5)?;
+        assert!(results.iter().all(|r| r.id != "doc1"));
+
+        db.clear()?;
+        assert!(db.search(&query, 5)?.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "hnsw")]
+    #[test]
+    fn test_hnsw_recall_matches_brute_force_top_1() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            hnsw: Some(hnsw::Config::default()),
+            ..Config::default()
+        };
+        let db = VectorDB::new(temp_file.path(), config)?;
+
+        for i in 0..200 {
+            let angle = i as f32 * 0.01;
+            let v = Vector::from_slice(&[angle.cos(), angle.sin(), (i as f32) * 0.001]);
+            db.insert(&format!("doc{i}"), v, r#"{}"#)?;
+        }
+
+        let query = Vector::from_slice(&[1.0, 0.0, 0.1]);
+        let hnsw_top1 = db.search(&query, 1)?;
+        let exact_top1 = db.search_exact(&query, 1)?;
+
+        assert_eq!(hnsw_top1.len(), 1);
+        assert_eq!(exact_top1.len(), 1);
+        // The approximate index should agree with brute force for an easy,
+        // well-separated top-1 query.
+        assert_eq!(hnsw_top1[0].id, exact_top1[0].id);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_observer_fires_once_per_row_of_flushed_batch_and_not_on_failed_write() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default())?;
+
+        let seen: Arc<Mutex<Vec<ChangeEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        db.add_observer(move |event| seen_clone.lock().push(event));
+
+        db.insert_batch(vec![
+            ("doc1".to_string(), Vector::from_slice(&[1.0, 2.0, 3.0]), r#"{}"#.to_string()),
+            ("doc2".to_string(), Vector::from_slice(&[4.0, 5.0, 6.0]), r#"{}"#.to_string()),
+        ])?;
+        db.flush()?;
+        assert_eq!(seen.lock().len(), 2, "one event per row in the flushed batch");
+
+        // Break the schema so the next write's transaction fails before commit.
+        db.conn.write().execute_batch("DROP TABLE vectors")?;
+        assert!(db.insert("doc3", Vector::from_slice(&[7.0, 8.0, 9.0]), r#"{}"#).is_err());
+        assert_eq!(seen.lock().len(), 2, "a failed write must not notify observers");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_restore_round_trips_rows() -> Result<(), VectorDBError> {
+        let src_file = NamedTempFile::new().unwrap();
+        let backup_file = NamedTempFile::new().unwrap();
+        let dst_file = NamedTempFile::new().unwrap();
+
+        let src = VectorDB::new(src_file.path(), Config::default())?;
+        for i in 0..5 {
+            let v = Vector::from_slice(&[i as f32, (i * 2) as f32, (i * 3) as f32]);
+            src.insert(&format!("doc{i}"), v, &format!(r#"{{"i":{i}}}"#))?;
+        }
+        src.backup(backup_file.path())?;
+
+        let dst = VectorDB::new(dst_file.path(), Config::default())?;
+        dst.insert("stale", Vector::from_slice(&[9.0, 9.0, 9.0]), r#"{}"#)?;
+        dst.restore(backup_file.path())?;
+
+        assert_eq!(dst.count()?, 5);
+        for i in 0..5 {
+            let (vector, metadata) = dst.get(&format!("doc{i}"))?.expect("row restored from backup");
+            assert_eq!(vector.as_slice(), &[i as f32, (i * 2) as f32, (i * 3) as f32]);
+            assert_eq!(metadata, format!(r#"{{"i":{i}}}"#));
+        }
+        // The row written before `restore` is not part of the snapshot and
+        // must not survive the wholesale overwrite.
+        assert!(dst.get("stale")?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_notifies_observers() -> Result<(), VectorDBError> {
+        let src_file = NamedTempFile::new().unwrap();
+        let backup_file = NamedTempFile::new().unwrap();
+        let dst_file = NamedTempFile::new().unwrap();
+
+        let src = VectorDB::new(src_file.path(), Config::default())?;
+        src.insert("doc1", Vector::from_slice(&[1.0, 2.0, 3.0]), r#"{}"#)?;
+        src.backup(backup_file.path())?;
+
+        let dst = VectorDB::new(dst_file.path(), Config::default())?;
+        let seen: Arc<Mutex<Vec<ChangeEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let seen_clone = seen.clone();
+        dst.add_observer(move |event| seen_clone.lock().push(event));
+
+        dst.restore(backup_file.path())?;
+
+        assert!(matches!(seen.lock().as_slice(), [ChangeEvent::Restored]));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "vtab")]
+    #[test]
+    fn test_vec_search_vtab_returns_nearest_neighbor() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default())?;
+        db.register_vec_search()?;
+
+        db.insert("doc1", Vector::from_slice(&[1.0, 0.0, 0.0]), r#"{"title": "one"}"#)?;
+        db.insert("doc2", Vector::from_slice(&[0.0, 1.0, 0.0]), r#"{"title": "two"}"#)?;
+
+        let query_bytes = rmp_serde::to_vec(&Vector::from_slice(&[1.0, 0.0, 0.0])).unwrap();
+        let ids: Vec<String> = db.query_sql(
+            "SELECT id FROM vec_search WHERE query = ?1 AND k = ?2",
+            rusqlite::params![query_bytes, 1],
+            |row| row.get(0),
+        )?;
+
+        assert_eq!(ids, vec!["doc1".to_string()]);
+
+        // A stale hit for a deleted id must not make the vtab scan error out.
+        db.delete("doc1")?;
+        let query_bytes = rmp_serde::to_vec(&Vector::from_slice(&[1.0, 0.0, 0.0])).unwrap();
+        let ids: Vec<String> = db.query_sql(
+            "SELECT id FROM vec_search WHERE query = ?1 AND k = ?2",
+            rusqlite::params![query_bytes, 5],
+            |row| row.get(0),
+        )?;
+        assert!(!ids.contains(&"doc1".to_string()));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,251 @@
+//! Int8 scalar quantization for compact storage and faster scoring.
+//!
+//! Vectors are stored as full `f32` MessagePack blobs by default, and every
+//! search deserializes and scores them in `f32`, which dominates both disk
+//! size and scan time at scale. In [`Quantization::Int8`] mode, each
+//! component is quantized per-vector to a `u8` via
+//! `q = round((x - min) / (max - min) * 255)` and stored alongside the
+//! vector's own `(min, max)` scale, roughly quartering storage. Exact `f32`
+//! storage remains available via [`Quantization::None`] (the default).
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Vector, VectorDBError};
+
+/// Storage mode for embeddings, set once via [`crate::Config::quantization`]
+/// for the lifetime of a [`crate::VectorDB`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantization {
+    /// Store the full, exact `f32` embedding (the default).
+    #[default]
+    None,
+    /// Quantize each component to a `u8`, scaled per-vector.
+    Int8,
+}
+
+/// The on-disk representation of an embedding, tagged by the mode it was
+/// stored under.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum StoredEmbedding {
+    F32(Vec<f32>),
+    Int8 { codes: Vec<u8>, min: f32, max: f32 },
+}
+
+impl StoredEmbedding {
+    pub(crate) fn encode(vector: &Vector, mode: Quantization) -> Self {
+        match mode {
+            Quantization::None => StoredEmbedding::F32(vector.as_slice().to_vec()),
+            Quantization::Int8 => {
+                let data = vector.as_slice();
+                let min = data.iter().copied().fold(f32::INFINITY, f32::min);
+                let max = data.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let range = if max > min { max - min } else { 1.0 };
+                let codes = data
+                    .iter()
+                    .map(|&x| (((x - min) / range) * 255.0).round().clamp(0.0, 255.0) as u8)
+                    .collect();
+                StoredEmbedding::Int8 { codes, min, max }
+            }
+        }
+    }
+
+    /// Dequantize back to an (approximate, for `Int8`) `f32` vector.
+    pub(crate) fn decode(&self) -> Vector {
+        match self {
+            StoredEmbedding::F32(data) => Vector::from_slice(data),
+            StoredEmbedding::Int8 { codes, min, max } => {
+                let range = max - min;
+                let data: Vec<f32> = codes
+                    .iter()
+                    .map(|&q| min + (q as f32 / 255.0) * range)
+                    .collect();
+                Vector::from_slice(&data)
+            }
+        }
+    }
+
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, VectorDBError> {
+        rmp_serde::to_vec(self).map_err(|e| VectorDBError::Serialization(e.to_string()))
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, VectorDBError> {
+        rmp_serde::from_slice(bytes).map_err(|e| VectorDBError::Serialization(e.to_string()))
+    }
+
+    /// Cosine similarity against `query`. For [`StoredEmbedding::Int8`] this
+    /// runs the quantized dot-product kernel instead of dequantizing through
+    /// a full `Vector` first.
+    pub(crate) fn cosine_similarity(&self, query: &Vector) -> f32 {
+        match self {
+            StoredEmbedding::F32(data) => Vector::from_slice(data).cosine_similarity(query),
+            StoredEmbedding::Int8 { codes, min, max } => {
+                quantized_cosine_similarity(codes, *min, *max, query.as_slice())
+            }
+        }
+    }
+}
+
+/// Sum and sum-of-squares of `codes`, widened from `u8` to `i64` and
+/// accumulated as pure integers (no dequantization, no floating point) since
+/// both reductions depend only on the stored codes, not the query.
+fn integer_code_sums(codes: &[u8]) -> (i64, i64) {
+    let mut sum_code: i64 = 0;
+    let mut sum_code_sq: i64 = 0;
+    for &c in codes {
+        let c = c as i64;
+        sum_code += c;
+        sum_code_sq += c * c;
+    }
+    (sum_code, sum_code_sq)
+}
+
+/// Combine the integer code reductions with the query-dependent terms into a
+/// cosine similarity, dequantizing exactly once via
+/// `a_i = min + code_i * scale`:
+///
+/// - `dot(a, q)   = min * sum(q) + scale * sum(code_i * q_i)`
+/// - `norm(a)^2   = n*min^2 + 2*min*scale*sum(code) + scale^2*sum(code^2)`
+fn combine(
+    min: f32,
+    scale: f32,
+    len: usize,
+    sum_code: i64,
+    sum_code_sq: i64,
+    dot_code_query: f32,
+    sum_query: f32,
+    norm_b: f32,
+) -> f32 {
+    let dot = min * sum_query + scale * dot_code_query;
+    let norm_a_sq =
+        (len as f32) * min * min + 2.0 * min * scale * sum_code as f32 + scale * scale * sum_code_sq as f32;
+
+    if norm_a_sq <= 0.0 || norm_b <= 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a_sq.sqrt() * norm_b.sqrt())
+}
+
+/// Cosine similarity between a quantized, stored embedding and an `f32`
+/// query. The codes' own sum and sum-of-squares (needed for the stored
+/// vector's norm) are reduced as plain integers via [`integer_code_sums`];
+/// only the query-dependent dot product widens codes to `f32` lanes, and the
+/// `min`/`scale` dequantization is applied once at the end rather than per
+/// component.
+#[cfg(feature = "simd")]
+fn quantized_cosine_similarity(codes: &[u8], min: f32, max: f32, query: &[f32]) -> f32 {
+    use wide::f32x8;
+
+    let range = if max > min { max - min } else { 1.0 };
+    let scale = range / 255.0;
+    let len = codes.len().min(query.len());
+    let simd_len = len / 8 * 8;
+
+    let (sum_code, sum_code_sq) = integer_code_sums(&codes[..len]);
+
+    let mut dot_code_query = 0.0f32;
+    let mut sum_query = 0.0f32;
+    let mut norm_b = 0.0f32;
+
+    for i in (0..simd_len).step_by(8) {
+        let code_v = f32x8::new([
+            codes[i] as f32,
+            codes[i + 1] as f32,
+            codes[i + 2] as f32,
+            codes[i + 3] as f32,
+            codes[i + 4] as f32,
+            codes[i + 5] as f32,
+            codes[i + 6] as f32,
+            codes[i + 7] as f32,
+        ]);
+        let query_v = f32x8::new([
+            query[i],
+            query[i + 1],
+            query[i + 2],
+            query[i + 3],
+            query[i + 4],
+            query[i + 5],
+            query[i + 6],
+            query[i + 7],
+        ]);
+
+        let dot_v = code_v * query_v;
+        let norm_b_v = query_v * query_v;
+
+        for j in 0..8 {
+            dot_code_query += dot_v.as_array_ref()[j];
+            sum_query += query_v.as_array_ref()[j];
+            norm_b += norm_b_v.as_array_ref()[j];
+        }
+    }
+
+    for i in simd_len..len {
+        dot_code_query += codes[i] as f32 * query[i];
+        sum_query += query[i];
+        norm_b += query[i] * query[i];
+    }
+
+    combine(min, scale, len, sum_code, sum_code_sq, dot_code_query, sum_query, norm_b)
+}
+
+#[cfg(not(feature = "simd"))]
+fn quantized_cosine_similarity(codes: &[u8], min: f32, max: f32, query: &[f32]) -> f32 {
+    let range = if max > min { max - min } else { 1.0 };
+    let scale = range / 255.0;
+    let len = codes.len().min(query.len());
+
+    let (sum_code, sum_code_sq) = integer_code_sums(&codes[..len]);
+
+    let mut dot_code_query = 0.0f32;
+    let mut sum_query = 0.0f32;
+    let mut norm_b = 0.0f32;
+
+    for i in 0..len {
+        dot_code_query += codes[i] as f32 * query[i];
+        sum_query += query[i];
+        norm_b += query[i] * query[i];
+    }
+
+    combine(min, scale, len, sum_code, sum_code_sq, dot_code_query, sum_query, norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vector;
+
+    #[test]
+    fn test_int8_round_trip_is_approximate() {
+        let v = Vector::from_slice(&[-1.0, 0.5, 0.0, 3.25, -2.75]);
+        let encoded = StoredEmbedding::encode(&v, Quantization::Int8);
+        let decoded = encoded.decode();
+
+        for (original, roundtripped) in v.as_slice().iter().zip(decoded.as_slice()) {
+            assert!(
+                (original - roundtripped).abs() < 0.05,
+                "{original} vs {roundtripped}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_int8_cosine_similarity_matches_f32_within_tolerance() {
+        let a = Vector::from_slice(&[1.0, 2.0, 3.0, -4.0, 0.5]);
+        let b = Vector::from_slice(&[0.9, 2.1, 2.8, -3.7, 0.6]);
+
+        let exact = a.cosine_similarity(&b);
+        let quantized = StoredEmbedding::encode(&a, Quantization::Int8).cosine_similarity(&b);
+
+        // Int8 quantization is lossy by design; the similarity score should
+        // still land close to the exact f32 result.
+        assert!((exact - quantized).abs() < 0.01, "exact={exact} quantized={quantized}");
+    }
+
+    #[test]
+    fn test_f32_mode_round_trips_exactly() {
+        let v = Vector::from_slice(&[1.0, -2.5, 3.75]);
+        let encoded = StoredEmbedding::encode(&v, Quantization::None);
+        let decoded = encoded.decode();
+        assert_eq!(decoded.as_slice(), v.as_slice());
+    }
+}
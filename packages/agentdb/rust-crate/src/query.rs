@@ -0,0 +1,302 @@
+//! Metadata-filtered search.
+//!
+//! `VectorDB::search` scores every row with no way to restrict candidates by
+//! their JSON `metadata` first, forcing callers to over-fetch and filter in
+//! Rust. [`SearchQuery`] compiles predicates over JSON metadata paths into a
+//! SQL `WHERE` clause using SQLite's JSON1 `json_extract`, so only matching
+//! rows are deserialized and scored.
+
+use rusqlite::types::Value;
+
+use crate::{SearchResult, Vector, VectorDB, VectorDBError};
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    Eq { path: String, value: Value },
+    Range { path: String, min: Option<Value>, max: Option<Value> },
+    In { path: String, values: Vec<Value> },
+}
+
+/// How a predicate combines with the one before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    And,
+    Or,
+}
+
+/// A predicate together with how it joins the one accumulated before it.
+/// The combinator on the first predicate is unused.
+#[derive(Debug, Clone)]
+struct PredicateEntry {
+    predicate: Predicate,
+    combinator: Combinator,
+}
+
+/// A builder for metadata-filtered k-nearest-neighbor search.
+///
+/// # Example
+/// ```rust,no_run
+/// # use sqlite_vector::{VectorDB, Config, Vector};
+/// # fn main() -> Result<(), sqlite_vector::VectorDBError> {
+/// # let db = VectorDB::new("vectors.db", Config::default())?;
+/// let query_vec = Vector::from_slice(&[0.1, 0.2, 0.3, 0.4]);
+///
+/// // Predicates combine with AND by default.
+/// let results = db
+///     .query()
+///     .filter_eq("category", "programming")
+///     .filter_range("price", None, Some(50.0))
+///     .knn(&query_vec, 10)?;
+///
+/// // `or()` joins the next predicate with OR instead, so AND and OR clauses
+/// // can be mixed within a single query.
+/// let results = db
+///     .query()
+///     .filter_eq("category", "programming")
+///     .or()
+///     .filter_eq("category", "fiction")
+///     .knn(&query_vec, 10)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SearchQuery<'db> {
+    db: &'db VectorDB,
+    predicates: Vec<PredicateEntry>,
+    pending_combinator: Combinator,
+}
+
+impl<'db> SearchQuery<'db> {
+    pub(crate) fn new(db: &'db VectorDB) -> Self {
+        Self {
+            db,
+            predicates: Vec::new(),
+            pending_combinator: Combinator::And,
+        }
+    }
+
+    /// Join the next predicate added with OR instead of the default AND.
+    /// Only affects the single predicate that follows; subsequent predicates
+    /// go back to combining with AND unless `or()` is called again.
+    pub fn or(mut self) -> Self {
+        self.pending_combinator = Combinator::Or;
+        self
+    }
+
+    fn push(&mut self, predicate: Predicate) {
+        self.predicates.push(PredicateEntry {
+            predicate,
+            combinator: self.pending_combinator,
+        });
+        self.pending_combinator = Combinator::And;
+    }
+
+    /// Require `json_extract(metadata, '$.<path>') = <value>`.
+    pub fn filter_eq(mut self, path: &str, value: &str) -> Self {
+        self.push(Predicate::Eq {
+            path: path.to_string(),
+            value: value.to_string().into(),
+        });
+        self
+    }
+
+    /// Require `json_extract(metadata, '$.<path>')` to fall within
+    /// `[min, max]`; either bound may be omitted for a one-sided range. If
+    /// both are omitted, no predicate is added at all (there's no bound left
+    /// to express, and emitting one would compile to an empty `()` group).
+    pub fn filter_range(mut self, path: &str, min: Option<f64>, max: Option<f64>) -> Self {
+        if min.is_none() && max.is_none() {
+            return self;
+        }
+        self.push(Predicate::Range {
+            path: path.to_string(),
+            min: min.map(Into::into),
+            max: max.map(Into::into),
+        });
+        self
+    }
+
+    /// Require `json_extract(metadata, '$.<path>')` to be one of `values`.
+    pub fn filter_in(mut self, path: &str, values: &[&str]) -> Self {
+        self.push(Predicate::In {
+            path: path.to_string(),
+            values: values.iter().map(|v| v.to_string().into()).collect(),
+        });
+        self
+    }
+
+    /// Render a single predicate's SQL fragment, appending its bound values
+    /// to `params` in the same order they appear in the fragment.
+    fn render_predicate(predicate: &Predicate, sql: &mut String, params: &mut Vec<Value>) {
+        match predicate {
+            Predicate::Eq { path, value } => {
+                sql.push_str("json_extract(metadata, ?) = ?");
+                params.push(json_path(path));
+                params.push(value.clone());
+            }
+            Predicate::Range { path, min, max } => {
+                let mut bounds = Vec::new();
+                let mut bound_params = Vec::new();
+                if let Some(min) = min {
+                    bounds.push("json_extract(metadata, ?) >= ?");
+                    bound_params.push(json_path(path));
+                    bound_params.push(min.clone());
+                }
+                if let Some(max) = max {
+                    bounds.push("json_extract(metadata, ?) <= ?");
+                    bound_params.push(json_path(path));
+                    bound_params.push(max.clone());
+                }
+                sql.push('(');
+                sql.push_str(&bounds.join(" AND "));
+                sql.push(')');
+                params.extend(bound_params);
+            }
+            Predicate::In { path, values } => {
+                if values.is_empty() {
+                    // `IN ()` is a syntax error, and an empty set can never
+                    // match anything, so short-circuit to a literal false
+                    // instead of binding a path nothing can compare against.
+                    sql.push('0');
+                } else {
+                    let placeholders = values.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+                    sql.push_str(&format!("json_extract(metadata, ?) IN ({placeholders})"));
+                    params.push(json_path(path));
+                    params.extend(values.iter().cloned());
+                }
+            }
+        }
+    }
+
+    fn compile(&self) -> (String, Vec<Value>) {
+        if self.predicates.is_empty() {
+            return (String::new(), Vec::new());
+        }
+
+        // Split into AND-joined runs at each `or()` boundary, so the fluent
+        // chain's intent survives SQL's AND-binds-tighter-than-OR precedence:
+        // `eq(a).or().eq(b).filter_range(c)` must compile to `(a) OR (b AND
+        // c)`, not the unparenthesized `a OR b AND (c)` it used to.
+        let mut groups: Vec<Vec<&Predicate>> = Vec::new();
+        for entry in &self.predicates {
+            match entry.combinator {
+                Combinator::Or => groups.push(vec![&entry.predicate]),
+                Combinator::And => match groups.last_mut() {
+                    Some(group) => group.push(&entry.predicate),
+                    None => groups.push(vec![&entry.predicate]),
+                },
+            }
+        }
+
+        let mut params = Vec::new();
+        let group_sqls: Vec<String> = groups
+            .into_iter()
+            .map(|group| {
+                let parts: Vec<String> = group
+                    .into_iter()
+                    .map(|predicate| {
+                        let mut sql = String::new();
+                        Self::render_predicate(predicate, &mut sql, &mut params);
+                        sql
+                    })
+                    .collect();
+                if parts.len() > 1 {
+                    format!("({})", parts.join(" AND "))
+                } else {
+                    parts.into_iter().next().unwrap_or_default()
+                }
+            })
+            .collect();
+
+        (format!("WHERE {}", group_sqls.join(" OR ")), params)
+    }
+
+    /// Run the k-nearest-neighbor scan over rows matching the accumulated
+    /// predicates, sorted by similarity score (descending).
+    pub fn knn(&self, query: &Vector, k: usize) -> Result<Vec<SearchResult>, VectorDBError> {
+        let (where_clause, params) = self.compile();
+        self.db.search_filtered(query, k, &where_clause, &params)
+    }
+}
+
+/// Build the JSON1 path expression for `path`, bound as a query parameter
+/// rather than interpolated into the SQL text so a path containing `'` or
+/// other SQL metacharacters can't break or inject into the statement.
+fn json_path(path: &str) -> Value {
+    Value::from(format!("$.{path}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, VectorDB};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_compile_parenthesizes_or_groups() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default()).unwrap();
+
+        // `a.or().b.c` should group as `(a) OR (b AND c)`, not the
+        // unparenthesized `a OR b AND (c)` SQL would otherwise bind as.
+        let (where_clause, _) = db
+            .query()
+            .filter_eq("category", "fiction")
+            .or()
+            .filter_eq("category", "news")
+            .filter_range("price", None, Some(10.0))
+            .compile();
+
+        assert_eq!(
+            where_clause,
+            "WHERE json_extract(metadata, ?) = ? OR (json_extract(metadata, ?) = ? AND (json_extract(metadata, ?) <= ?))"
+        );
+    }
+
+    #[test]
+    fn test_filter_range_with_no_bounds_emits_no_predicate() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default()).unwrap();
+
+        let (where_clause, params) = db.query().filter_range("price", None, None).compile();
+
+        assert_eq!(where_clause, "");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_compile_empty_filter_in_is_not_a_syntax_error() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default()).unwrap();
+
+        let (where_clause, params) = db.query().filter_in("category", &[]).compile();
+
+        assert!(!where_clause.contains("IN ()"));
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn test_filtered_knn_matches_only_predicate() -> Result<(), VectorDBError> {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = VectorDB::new(temp_file.path(), Config::default())?;
+
+        db.insert("doc1", Vector::from_slice(&[1.0, 0.0, 0.0]), r#"{"category": "fiction"}"#)?;
+        db.insert("doc2", Vector::from_slice(&[1.0, 0.0, 0.0]), r#"{"category": "news"}"#)?;
+
+        let query = Vector::from_slice(&[1.0, 0.0, 0.0]);
+        let results = db.query().filter_eq("category", "fiction").knn(&query, 10)?;
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "doc1");
+
+        // An empty `filter_in` can never match anything, but must not error.
+        let results = db.query().filter_in("category", &[]).knn(&query, 10)?;
+        assert!(results.is_empty());
+
+        // A `filter_range` with no bounds at all must not compile to an
+        // empty, syntactically invalid SQL group.
+        let results = db.query().filter_range("price", None, None).knn(&query, 10)?;
+        assert_eq!(results.len(), 2);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,29 @@
 //! QUIC-based synchronization module
 //!
 //! This module provides distributed synchronization capabilities using QUIC protocol.
+//! Replication is changeset-based: rather than replaying individual `Insert`/`Delete`
+//! operations, peers exchange binary SQLite session-extension changesets captured from
+//! the `vectors` table, which correctly and compactly encode any combination of inserts,
+//! updates, and deletes (including `clear()` and `INSERT OR REPLACE` upserts) in a single
+//! blob.
 
 #[cfg(feature = "quic-sync")]
 use std::net::SocketAddr;
+#[cfg(feature = "quic-sync")]
+use std::sync::Arc;
 
+#[cfg(feature = "quic-sync")]
+use parking_lot::{ArcRwLockWriteGuard, RawRwLock, RwLock};
+#[cfg(feature = "quic-sync")]
+use rusqlite::session::{ChangesetItem, ConflictAction, ConflictType, Session};
+#[cfg(feature = "quic-sync")]
+use rusqlite::Connection;
 #[cfg(feature = "quic-sync")]
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "quic-sync")]
+use crate::{VectorDB, VectorDBError};
+
 /// QUIC synchronization configuration
 #[cfg(feature = "quic-sync")]
 #[derive(Debug, Clone)]
@@ -22,17 +38,220 @@ pub struct SyncConfig {
 #[cfg(feature = "quic-sync")]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum SyncMessage {
-    /// Insert operation
-    Insert {
-        id: String,
-        embedding: Vec<u8>,
-        metadata: String,
+    /// A changeset capturing every mutation to the `vectors` table since the
+    /// session was last taken, tagged with the originating peer's Lamport
+    /// counter so conflicting applies can be resolved deterministically.
+    Changeset {
+        /// Binary changeset produced by the SQLite session extension.
+        bytes: Vec<u8>,
+        /// Lamport counter of the peer at the time the changeset was taken.
+        lamport: u64,
     },
-    /// Delete operation
-    Delete { id: String },
     /// Heartbeat
     Heartbeat { timestamp: u64 },
 }
 
-// QUIC sync implementation would go here
-// This is a placeholder for the full implementation
+/// Apply a changeset received from a peer to `conn`, resolving `DATA` and
+/// `CONFLICT` collisions with last-writer-wins semantics based on `lamport`.
+///
+/// Rows whose local `lamport` is newer than the incoming change's are kept
+/// as-is; otherwise (including a tie) the incoming change wins.
+#[cfg(feature = "quic-sync")]
+pub(crate) fn apply_changeset(
+    conn: &Connection,
+    bytes: &[u8],
+    lamport: u64,
+) -> Result<(), VectorDBError> {
+    conn.apply(
+        &mut bytes.as_ref(),
+        None::<fn(&str) -> bool>,
+        |conflict_type, item| match conflict_type {
+            ConflictType::SQLITE_CHANGESET_DATA | ConflictType::SQLITE_CHANGESET_CONFLICT => {
+                if lamport_wins(&item, lamport) {
+                    ConflictAction::SQLITE_CHANGESET_REPLACE
+                } else {
+                    ConflictAction::SQLITE_CHANGESET_OMIT
+                }
+            }
+            _ => ConflictAction::SQLITE_CHANGESET_OMIT,
+        },
+    )?;
+    Ok(())
+}
+
+/// Column index of `lamport` in the `vectors` table (id, embedding, metadata,
+/// created_at, lamport), read off the conflicting row to compare against the
+/// incoming change's counter.
+#[cfg(feature = "quic-sync")]
+const LAMPORT_COLUMN: usize = 4;
+
+/// Last-writer-wins: the incoming change replaces the local row only if its
+/// `lamport` is at least as new as the local row's own `lamport`. If the
+/// conflicting local row's `lamport` can't be read, it is treated as older
+/// than any incoming change and the incoming change wins.
+#[cfg(feature = "quic-sync")]
+fn lamport_wins(item: &ChangesetItem, incoming: u64) -> bool {
+    let local_lamport = item
+        .conflict(LAMPORT_COLUMN)
+        .ok()
+        .and_then(|value| value.as_i64().ok())
+        .unwrap_or(i64::MIN);
+    incoming as i64 >= local_lamport
+}
+
+/// A session attached to the `vectors` table, accumulating a changeset across
+/// a batch of mutations made through the `VectorDB` it was started from.
+///
+/// # Safety
+/// `Session` borrows the `Connection` for `'static`. That's only sound
+/// because `guard` — an owned, `Arc`-backed write guard — is held for
+/// `ActiveSession`'s entire lifetime: it pins the `Connection` at a stable
+/// address (the `Arc` it clones is never freed or moved while the guard
+/// lives) and, being a write guard, guarantees no other `&Connection` or
+/// `&mut Connection` can exist anywhere else for as long as the session is
+/// attached — unlike a connection shared only via the `Arc` and re-locked
+/// per call, which would let `insert`/`delete`/`flush`'s own
+/// `conn.transaction()` alias this borrow. `guard` and `session` must never
+/// be split apart or reordered: fields drop in declaration order, so
+/// `session` is declared first and is dropped before `guard` releases the
+/// lock; dropping `guard` first would leave `session` holding a dangling
+/// reference.
+#[cfg(feature = "quic-sync")]
+pub struct ActiveSession {
+    session: Session<'static>,
+    guard: ArcRwLockWriteGuard<RawRwLock, Connection>,
+}
+
+// Safety: the session only ever touches the `Connection` through `guard`,
+// which this struct owns outright, so moving `ActiveSession` to another
+// thread carries no thread-local state along with it.
+#[cfg(feature = "quic-sync")]
+unsafe impl Send for ActiveSession {}
+
+/// Start a session against `conn`'s `vectors` table, returning a handle whose
+/// changes can later be collected with [`take_changeset`]. Holds `conn`'s
+/// write lock until then (see [`ActiveSession`]'s doc comment);
+/// `VectorDB::with_write_conn` routes mutations through
+/// [`ActiveSession::connection_mut`] while a session is active instead of
+/// re-acquiring that lock, so `insert`/`delete`/`clear`/`flush` work during
+/// a session. Anything that locks `conn` directly (`search`, `get`, `count`,
+/// `apply_changeset`, HNSW index upkeep, ...) waits on that lock until the
+/// session is taken.
+#[cfg(feature = "quic-sync")]
+pub(crate) fn begin_session(conn: Arc<RwLock<Connection>>) -> Result<ActiveSession, VectorDBError> {
+    let guard = conn.write_arc();
+    // Safety: see `ActiveSession`'s doc comment.
+    let conn_ref: &'static Connection = unsafe { std::mem::transmute::<&Connection, &'static Connection>(&guard) };
+    let mut session = Session::new(conn_ref)?;
+    session.attach(Some("vectors"))?;
+
+    Ok(ActiveSession { guard, session })
+}
+
+#[cfg(feature = "quic-sync")]
+impl ActiveSession {
+    /// Mutable access to the connection this session is attached to, so
+    /// callers that need `&mut Connection` (e.g. `conn.transaction()`) reuse
+    /// the lock `guard` already holds instead of taking a second one.
+    pub(crate) fn connection_mut(&mut self) -> &mut Connection {
+        &mut self.guard
+    }
+}
+
+/// Drain `active` into a binary changeset, or `None` if nothing changed.
+/// Releases the write lock `active` was holding.
+#[cfg(feature = "quic-sync")]
+pub(crate) fn take_changeset(active: ActiveSession) -> Result<Option<Vec<u8>>, VectorDBError> {
+    if active.session.is_empty() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    active.session.changeset_strm(&mut bytes)?;
+    Ok(Some(bytes))
+}
+
+/// Enable QUIC-based synchronization for `db` against `config`.
+///
+/// This intentionally does *not* call [`VectorDB::begin_session`] itself.
+/// [`ActiveSession`] holds `db`'s connection write lock for its entire
+/// lifetime (see its doc comment), and nothing here would ever call
+/// [`VectorDB::take_changeset`] to release it — a background loop that
+/// periodically re-attached the session per flush, draining it between
+/// cycles, would do that, but needs `VectorDB` to be `Send` so it can run on
+/// another thread, which it isn't: `conn: Arc<RwLock<Connection>>` embeds a
+/// `rusqlite::Connection`, and `Connection` is `Send` but not `Sync`, which
+/// makes `Arc<RwLock<Connection>>` (and so `VectorDB`) neither `Send` nor
+/// `Sync`. Starting an unattended session here, as this function previously
+/// did, left the write lock held forever, and every read (`search`, `get`,
+/// `count`, `backup*`, ...) goes through `self.conn.read()` directly rather
+/// than the active session — so any read after `enable_sync` deadlocked
+/// permanently.
+///
+/// Until `VectorDB` can move across threads, callers drive
+/// `begin_session`/`take_changeset` themselves around their own flush
+/// schedule, exactly as `examples/quic_sync.rs` does. This function accepts
+/// `config` but neither stores nor uses it, and opens no connection to
+/// `config.peers`.
+#[cfg(feature = "quic-sync")]
+pub async fn enable_sync(db: &VectorDB, config: SyncConfig) -> Result<(), VectorDBError> {
+    // Networking is intentionally out of scope here: wiring an actual QUIC
+    // endpoint (quinn) to stream `SyncMessage::Changeset` values to
+    // `config.peers` and apply inbound ones via `apply_changeset` is not
+    // done by this crate.
+    let _ = db;
+    let _ = config;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "quic-sync"))]
+mod tests {
+    use super::*;
+    use crate::{Config, Vector};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_changeset_round_trip_propagates_insert() -> Result<(), VectorDBError> {
+        let file_a = NamedTempFile::new().unwrap();
+        let file_b = NamedTempFile::new().unwrap();
+        let db_a = VectorDB::new(file_a.path(), Config::default())?;
+        let db_b = VectorDB::new(file_b.path(), Config::default())?;
+
+        db_a.begin_session()?;
+        db_a.insert("doc1", Vector::from_slice(&[1.0, 2.0, 3.0]), r#"{"peer":"a"}"#)?;
+        let changeset = db_a.take_changeset()?.expect("session captured a write");
+
+        db_b.apply_changeset(&changeset, 1)?;
+
+        let (vector, metadata) = db_b.get("doc1")?.expect("row replicated from a's changeset");
+        assert_eq!(vector.as_slice(), &[1.0, 2.0, 3.0]);
+        assert_eq!(metadata, r#"{"peer":"a"}"#);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lamport_wins_keeps_newer_local_row_on_conflict() -> Result<(), VectorDBError> {
+        let file_a = NamedTempFile::new().unwrap();
+        let file_b = NamedTempFile::new().unwrap();
+        let db_a = VectorDB::new(file_a.path(), Config::default())?;
+        let db_b = VectorDB::new(file_b.path(), Config::default())?;
+
+        // `b` writes the row twice locally, landing it at lamport 2, before
+        // `a`'s (lamport 1) changeset for the same id arrives.
+        db_b.insert("doc1", Vector::from_slice(&[0.0, 0.0, 0.0]), r#"{"rev":1}"#)?;
+        db_b.insert("doc1", Vector::from_slice(&[9.0, 9.0, 9.0]), r#"{"rev":2}"#)?;
+
+        db_a.begin_session()?;
+        db_a.insert("doc1", Vector::from_slice(&[1.0, 2.0, 3.0]), r#"{"rev":"a"}"#)?;
+        let changeset = db_a.take_changeset()?.expect("session captured a write");
+
+        db_b.apply_changeset(&changeset, 1)?;
+
+        // `b`'s own, newer write must survive the stale incoming change.
+        let (vector, metadata) = db_b.get("doc1")?.expect("row still present");
+        assert_eq!(vector.as_slice(), &[9.0, 9.0, 9.0]);
+        assert_eq!(metadata, r#"{"rev":2}"#);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+//! `vec_search` SQLite virtual table.
+//!
+//! Registers an eponymous-only virtual table so callers can run
+//! `SELECT id, metadata, distance FROM vec_search WHERE query = ? AND k = 10`
+//! and join the result against their own tables, instead of post-filtering
+//! `VectorDB::search` results in Rust. `query` must be a [`crate::Vector`]
+//! serialized as MessagePack (`rmp_serde::to_vec(&vector)`); `k` is the
+//! number of rows to return. Use [`crate::VectorDB::query_sql`] to run
+//! that SQL: this is an in-process `sqlite3_create_module` registration, not
+//! a loadable extension, so `vec_search` is only reachable through this
+//! crate's own connection, not from an external `sqlite3`/`load_extension`
+//! client.
+
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rusqlite::vtab::{
+    eponymous_only_module, Context, IndexInfo, VTab, VTabConnection, VTabCursor, Values,
+};
+use rusqlite::{Connection, Error as SqliteError, OptionalExtension};
+
+use crate::{Vector, VectorDBError};
+
+/// A similarity scan over the `vectors` table: brute-force, or backed by an
+/// HNSW index when one is configured on the owning [`crate::VectorDB`].
+///
+/// Neither `Send` nor `Sync`: it closes over `Arc<RwLock<Connection>>`
+/// (directly, or via `HnswIndex`, which embeds one), and `Connection` isn't
+/// `Sync`, which makes `RwLock<Connection>` itself not `Sync` and therefore
+/// `Arc<RwLock<Connection>>` neither `Send` nor `Sync` (`Arc<T>` needs
+/// `T: Send + Sync` for either). That's fine here — `rusqlite::create_module`
+/// places no `Send`/`Sync` bound on its aux data, and a `vec_search` virtual
+/// table is only ever driven through the single `Connection` it's registered
+/// on, never concurrently from multiple threads.
+pub(crate) type ScanFn = dyn Fn(&Vector, usize) -> Result<Vec<(String, f32)>, VectorDBError>;
+
+/// Aux data threaded through `sqlite3_create_module` into every `vec_search`
+/// table connected on `conn`.
+#[derive(Clone)]
+pub(crate) struct VecSearchAux {
+    pub(crate) conn: Arc<RwLock<Connection>>,
+    pub(crate) scan: Arc<ScanFn>,
+}
+
+/// Register the `vec_search` virtual table module on `conn`.
+pub(crate) fn register(conn: &Connection, aux: VecSearchAux) -> rusqlite::Result<()> {
+    conn.create_module("vec_search", eponymous_only_module::<VecSearchTab>(), Some(aux))
+}
+
+#[repr(C)]
+pub(crate) struct VecSearchTab {
+    base: rusqlite::vtab::sqlite3_vtab,
+    aux: VecSearchAux,
+}
+
+// Column positions in the `vec_search` schema declared by `connect`.
+// `best_index` pushes down constraints on `COL_QUERY`/`COL_K` as `filter`'s
+// first and second arguments; `column` serves the other three.
+const COL_ID: c_int = 0;
+const COL_METADATA: c_int = 1;
+const COL_DISTANCE: c_int = 2;
+const COL_QUERY: c_int = 3;
+const COL_K: c_int = 4;
+
+unsafe impl<'vtab> VTab<'vtab> for VecSearchTab {
+    type Aux = VecSearchAux;
+    type Cursor = VecSearchCursor<'vtab>;
+
+    fn connect(
+        _db: &mut VTabConnection,
+        aux: Option<&VecSearchAux>,
+        _args: &[&[u8]],
+    ) -> rusqlite::Result<(String, Self)> {
+        let aux = aux
+            .cloned()
+            .ok_or_else(|| SqliteError::ModuleError("vec_search: missing aux data".into()))?;
+
+        let schema = "CREATE TABLE vec_search (
+            id TEXT,
+            metadata TEXT,
+            distance REAL,
+            query HIDDEN,
+            k HIDDEN
+        )";
+
+        Ok((
+            schema.to_owned(),
+            VecSearchTab {
+                base: rusqlite::vtab::sqlite3_vtab::default(),
+                aux,
+            },
+        ))
+    }
+
+    fn best_index(&self, info: &mut IndexInfo) -> rusqlite::Result<()> {
+        let mut query_arg = None;
+        let mut k_arg = None;
+
+        for (i, constraint) in info.constraints().enumerate() {
+            if !constraint.is_usable() {
+                continue;
+            }
+            match constraint.column() {
+                COL_QUERY if constraint.operator() == rusqlite::vtab::IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_EQ => {
+                    query_arg = Some(i);
+                }
+                COL_K if constraint.operator() == rusqlite::vtab::IndexConstraintOp::SQLITE_INDEX_CONSTRAINT_EQ => {
+                    k_arg = Some(i);
+                }
+                _ => {}
+            }
+        }
+
+        // Both `query` and `k` must be pushed down; without them there is no
+        // way to run the scan.
+        if let (Some(query_idx), Some(k_idx)) = (query_arg, k_arg) {
+            let mut usage = info.constraint_usage(query_idx);
+            usage.set_argv_index(1);
+            usage.set_omit(true);
+            let mut usage = info.constraint_usage(k_idx);
+            usage.set_argv_index(2);
+            usage.set_omit(true);
+            info.set_estimated_cost(1.0);
+        } else {
+            // No usable plan: make it prohibitively expensive. If SQLite
+            // picks it anyway, `filter` receives no arguments and reports an
+            // error rather than silently scanning nothing.
+            info.set_estimated_cost(f64::MAX);
+        }
+
+        Ok(())
+    }
+
+    fn open(&'vtab mut self) -> rusqlite::Result<VecSearchCursor<'vtab>> {
+        Ok(VecSearchCursor {
+            base: rusqlite::vtab::sqlite3_vtab_cursor::default(),
+            aux: &self.aux,
+            rows: Vec::new(),
+            index: 0,
+            _marker: PhantomData,
+        })
+    }
+}
+
+#[repr(C)]
+pub(crate) struct VecSearchCursor<'vtab> {
+    // Must be the first field: SQLite writes its own `pVtab` backpointer into
+    // this slot right after `xOpen` returns, so anything placed ahead of it
+    // gets silently clobbered (and the real fields land at the wrong offset).
+    base: rusqlite::vtab::sqlite3_vtab_cursor,
+    aux: &'vtab VecSearchAux,
+    rows: Vec<(String, String, f32)>,
+    index: usize,
+    _marker: PhantomData<&'vtab VecSearchTab>,
+}
+
+unsafe impl<'vtab> VTabCursor for VecSearchCursor<'vtab> {
+    fn filter(&mut self, _idx_num: c_int, _idx_str: Option<&str>, args: &Values<'_>) -> rusqlite::Result<()> {
+        if args.len() < 2 {
+            return Err(SqliteError::ModuleError(
+                "vec_search: both `query` and `k` constraints are required".into(),
+            ));
+        }
+        let query_bytes: Vec<u8> = args.get(0)?;
+        let k: i64 = args.get(1)?;
+
+        let query: Vector = rmp_serde::from_slice(&query_bytes)
+            .map_err(|e| SqliteError::ModuleError(format!("vec_search: invalid query vector: {e}")))?;
+
+        let hits = (self.aux.scan)(&query, k.max(0) as usize)
+            .map_err(|e| SqliteError::ModuleError(format!("vec_search: scan failed: {e}")))?;
+
+        let conn = self.aux.conn.read();
+        // A hit can lag a concurrent delete/clear that hasn't pruned the
+        // graph yet; skip ids no longer in `vectors` instead of erroring.
+        self.rows = hits
+            .into_iter()
+            .filter_map(|(id, score)| {
+                let metadata: Option<String> = match conn
+                    .query_row(
+                        "SELECT metadata FROM vectors WHERE id = ?1",
+                        rusqlite::params![id],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                {
+                    Ok(metadata) => metadata,
+                    Err(e) => return Some(Err(e)),
+                };
+                metadata.map(|metadata| Ok((id, metadata, 1.0 - score)))
+            })
+            .collect::<rusqlite::Result<_>>()?;
+        self.index = 0;
+
+        Ok(())
+    }
+
+    fn next(&mut self) -> rusqlite::Result<()> {
+        self.index += 1;
+        Ok(())
+    }
+
+    fn eof(&self) -> bool {
+        self.index >= self.rows.len()
+    }
+
+    fn column(&self, ctx: &mut Context, col: c_int) -> rusqlite::Result<()> {
+        let Some((id, metadata, distance)) = self.rows.get(self.index) else {
+            return Ok(());
+        };
+        match col {
+            COL_ID => ctx.set_result(id),
+            COL_METADATA => ctx.set_result(metadata),
+            COL_DISTANCE => ctx.set_result(distance),
+            _ => Ok(()),
+        }
+    }
+
+    fn rowid(&self) -> rusqlite::Result<i64> {
+        Ok(self.index as i64)
+    }
+}